@@ -0,0 +1,223 @@
+//! Tunable triviality rules loaded from an INI-style configuration file.
+//!
+//! Beyond plain `key = value` pairs grouped into `[sections]`, the format understands two
+//! Mercurial-style directives:
+//!
+//! * `%include path` splices in another config file, resolved relative to the including file and
+//!   applied recursively (with cycle detection).
+//! * `%unset key` drops a key inherited from an earlier file or section so includes can be
+//!   composed and then selectively overridden.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+/// The resolved set of thresholds threaded into the detection functions.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub bin: BinConfig,
+    pub lib: LibConfig,
+}
+
+#[derive(Clone, Debug)]
+pub struct BinConfig {
+    /// A `main` whose body exceeds this many newlines is non-trivial.
+    pub max_main_lines: usize,
+    /// Macros whose sole presence keeps a `main` trivial (without the trailing `!`).
+    pub allowed_trivial_macros: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LibConfig {
+    /// Whether a library with no `pub` items should be treated as trivial.
+    pub trivial_if_no_pub: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bin: BinConfig {
+                max_main_lines: 2,
+                allowed_trivial_macros: vec!["println".to_string()],
+            },
+            lib: LibConfig {
+                trivial_if_no_pub: true,
+            },
+        }
+    }
+}
+
+impl Config {
+    /// Loads a config file, resolving `%include`/`%unset` directives before applying the result
+    /// on top of the defaults.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut values = HashMap::new();
+        let mut stack = HashSet::new();
+        parse_into(path.as_ref(), &mut values, &mut stack)?;
+        Self::from_values(values)
+    }
+
+    fn from_values(values: HashMap<String, String>) -> Result<Self> {
+        let mut config = Config::default();
+
+        for (key, value) in values {
+            match key.as_str() {
+                "bin.max_main_lines" => {
+                    config.bin.max_main_lines = value
+                        .parse()
+                        .with_context(|| format!("parsing bin.max_main_lines = {value:?}"))?;
+                }
+                "bin.allowed_trivial_macros" => {
+                    config.bin.allowed_trivial_macros = value
+                        .split(',')
+                        .map(|macro_name| macro_name.trim().to_string())
+                        .filter(|macro_name| !macro_name.is_empty())
+                        .collect();
+                }
+                "lib.trivial_if_no_pub" => {
+                    config.lib.trivial_if_no_pub = parse_bool(&value)?;
+                }
+                other => bail!("unknown config key `{other}`"),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_into(
+    path: &Path,
+    values: &mut HashMap<String, String>,
+    stack: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("resolving config file {path:?}"))?;
+    if !stack.insert(canonical.clone()) {
+        bail!("cyclic %include detected at {path:?}");
+    }
+
+    let content = fs::read_to_string(&canonical)
+        .with_context(|| format!("reading config file {canonical:?}"))?;
+    let dir = canonical
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("config file {canonical:?} has no parent directory"))?;
+
+    let mut section = String::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(target) = line.strip_prefix("%include") {
+            let target = target.trim();
+            if target.is_empty() {
+                bail!("%include requires a path");
+            }
+            parse_into(&dir.join(target), values, stack)?;
+        } else if let Some(key) = line.strip_prefix("%unset") {
+            let key = key.trim();
+            if key.is_empty() {
+                bail!("%unset requires a key");
+            }
+            values.remove(&qualify(&section, key));
+        } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_string();
+        } else if let Some((key, value)) = line.split_once('=') {
+            values.insert(qualify(&section, key.trim()), value.trim().to_string());
+        } else {
+            bail!("malformed config line: {line}");
+        }
+    }
+
+    stack.remove(&canonical);
+    Ok(())
+}
+
+fn qualify(section: &str, key: &str) -> String {
+    format!("{section}.{key}")
+}
+
+fn parse_bool(raw: &str) -> Result<bool> {
+    match raw {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => bail!("expected `true` or `false`, found `{other}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Creates a fresh, uniquely named temporary directory for a single test.
+    fn temp_dir() -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!(
+            "triviality-config-{}-{id}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn sections_tune_thresholds() {
+        let dir = temp_dir();
+        let path = write(
+            &dir,
+            "config.conf",
+            "[bin]\nmax_main_lines = 5\nallowed_trivial_macros = println, eprintln\n[lib]\ntrivial_if_no_pub = false\n",
+        );
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.bin.max_main_lines, 5);
+        assert_eq!(config.bin.allowed_trivial_macros, vec!["println", "eprintln"]);
+        assert!(!config.lib.trivial_if_no_pub);
+    }
+
+    #[test]
+    fn include_then_unset_drops_inherited_key() {
+        let dir = temp_dir();
+        write(&dir, "child.conf", "[bin]\n%unset max_main_lines\n");
+        let parent = write(
+            &dir,
+            "parent.conf",
+            "[bin]\nmax_main_lines = 9\n%include child.conf\n",
+        );
+
+        // The `%unset` in the included file must drop the key scoped to `[bin]`, reverting to the
+        // default rather than the inherited 9.
+        let config = Config::load(&parent).unwrap();
+        assert_eq!(config.bin.max_main_lines, Config::default().bin.max_main_lines);
+    }
+
+    #[test]
+    fn include_cycles_are_detected() {
+        let dir = temp_dir();
+        write(&dir, "a.conf", "%include b.conf\n");
+        let b = write(&dir, "b.conf", "%include a.conf\n");
+        assert!(Config::load(&b).is_err());
+    }
+
+    #[test]
+    fn unknown_key_is_an_error() {
+        let dir = temp_dir();
+        let path = write(&dir, "config.conf", "[bin]\nbogus = 1\n");
+        assert!(Config::load(&path).is_err());
+    }
+}
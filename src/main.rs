@@ -1,17 +1,24 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeSet, HashMap, HashSet},
     ffi::OsStr,
+    fmt,
     fs::File,
     io::Read,
+    ops::Range,
     os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
 };
 
+mod config;
+mod query;
+mod rule;
+
+use anyhow::Context;
 use clap::Parser;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use semver::Version;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
 /// Scans paths containing one or more extracted crate files to see if those crates implement
@@ -22,65 +29,304 @@ struct Opt {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Explain why each non-trivial crate was classified as such.
+    #[arg(long)]
+    explain: bool,
+
+    /// Only report crates matching the given query expression.
+    #[arg(long)]
+    query: Option<String>,
+
+    /// Load tunable triviality rules from a config file.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Scan a single manifest (or workspace root) instead of walking directories.
+    #[arg(long)]
+    manifest_path: Option<PathBuf>,
+
+    /// Tree-sitter query files defining additional non-triviality rules.
+    #[arg(long)]
+    rule: Vec<PathBuf>,
+
+    /// Format in which each scanned crate is reported.
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+
     /// Paths to scan.
-    #[arg(required = true)]
+    #[arg(required_unless_present = "manifest_path")]
     paths: Vec<PathBuf>,
 }
 
+/// How each scanned crate is emitted to stdout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+enum MessageFormat {
+    /// Freeform human-readable output (the default).
+    #[default]
+    Human,
+    /// One terse `name trivial|non-trivial` line per crate.
+    Short,
+    /// One JSON object per crate (NDJSON).
+    Json,
+}
+
 fn main() -> anyhow::Result<()> {
     let opt = Opt::parse();
 
-    for path in opt.paths.iter() {
-        let crate_roots = WalkDir::new(path)
-            .into_iter()
-            .filter_ok(|entry| entry.file_type().is_file() && is_manifest(entry.file_name()))
-            .map_ok(|entry| -> anyhow::Result<_> {
-                let manifest_path = entry.path();
-                let root = manifest_path
-                    .parent()
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("unexpected lack of parent for {manifest_path:?}")
-                    })?
-                    .to_path_buf();
-
-                let mut raw = String::new();
-                File::open(manifest_path)?.read_to_string(&mut raw)?;
-                let manifest: Manifest = toml::from_str(&raw)?;
-
-                Ok(Root { root, manifest })
-            })
-            .flatten()
-            .fold_ok(HashMap::<String, BTreeSet<Root>>::new(), |mut acc, root| {
-                acc.entry(root.manifest.package.name.clone())
-                    .or_default()
-                    .insert(root);
-                acc
-            })?;
-
-        // FIXME: do something to not scan nested manifests within crate files.
-
-        for (name, version_roots) in crate_roots.into_iter() {
-            if crate_has_non_trivial_code(version_roots.into_iter())? {
-                if opt.verbose {
-                    println!("non trivial: {name}");
-                }
-            } else {
-                println!("{name}");
+    let query = opt
+        .query
+        .as_deref()
+        .map(query::Query::parse)
+        .transpose()?;
+
+    let config = match &opt.config {
+        Some(path) => config::Config::load(path)?,
+        None => config::Config::default(),
+    };
+
+    let rules = rule::Rules::load(&opt.rule)?;
+
+    let mut crate_roots = HashMap::<String, BTreeSet<Root>>::new();
+
+    if let Some(manifest_path) = &opt.manifest_path {
+        collect_roots(manifest_path, &mut crate_roots)?;
+    } else {
+        collect_from_paths(&opt.paths, &mut crate_roots)?;
+    }
+
+    for (name, version_roots) in crate_roots.into_iter() {
+        let report = CrateReport::scan(name, version_roots, &config, &rules)?;
+        if let Some(query) = &query {
+            if !query.matches(&report.metadata()) {
+                continue;
             }
         }
+        report.emit(&opt)?;
+    }
+
+    Ok(())
+}
+
+/// Walks the given paths for manifests, recording each discovered package while pruning any
+/// nested or vendored manifest beneath an already-discovered package root.
+fn collect_from_paths(
+    paths: &[PathBuf],
+    crate_roots: &mut HashMap<String, BTreeSet<Root>>,
+) -> anyhow::Result<()> {
+    let mut manifests = Vec::new();
+    for path in paths.iter() {
+        for entry in WalkDir::new(path) {
+            let entry = entry?;
+            if entry.file_type().is_file() && is_manifest(entry.file_name()) {
+                manifests.push(entry.path().to_path_buf());
+            }
+        }
+    }
+
+    // Process shallower manifests first so a package (or workspace) root is always seen before
+    // any nested or vendored manifest beneath it, regardless of readdir order.
+    manifests.sort_by_key(|path| path.components().count());
+
+    let mut roots = Vec::<PathBuf>::new();
+    for manifest in manifests {
+        let dir = manifest
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("unexpected lack of parent for {manifest:?}"))?;
+
+        // Skip any manifest living under an already-discovered package root.
+        if roots.iter().any(|root| dir.starts_with(root)) {
+            continue;
+        }
+
+        collect_roots(&manifest, crate_roots)?;
+        roots.push(dir.to_path_buf());
+    }
+
+    Ok(())
+}
+
+/// Reads a manifest and records the packages it describes, expanding `[workspace]` members into
+/// their own package manifests.
+fn collect_roots(
+    manifest_path: &Path,
+    crate_roots: &mut HashMap<String, BTreeSet<Root>>,
+) -> anyhow::Result<()> {
+    let root = manifest_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("unexpected lack of parent for {manifest_path:?}"))?
+        .to_path_buf();
+
+    let mut raw = String::new();
+    File::open(manifest_path)?.read_to_string(&mut raw)?;
+    let manifest: Manifest =
+        toml::from_str(&raw).with_context(|| format!("parsing manifest {manifest_path:?}"))?;
+
+    if let Some(workspace) = &manifest.workspace {
+        for member in resolve_workspace_members(&root, workspace)? {
+            collect_roots(&member, crate_roots)?;
+        }
+    }
+
+    // A virtual workspace manifest has no `[package]`; only real packages become roots.
+    if let Some(package) = &manifest.package {
+        let name = package.name.clone();
+        crate_roots
+            .entry(name)
+            .or_default()
+            .insert(Root { root, manifest });
     }
 
     Ok(())
 }
 
-fn crate_has_non_trivial_code(roots: impl Iterator<Item = Root>) -> anyhow::Result<bool> {
-    for root in roots {
-        if root.has_non_trivial_code()? {
-            return Ok(true);
+/// Expands a workspace's `members`/`exclude` globs into the paths of the member manifests.
+fn resolve_workspace_members(
+    root: &Path,
+    workspace: &Workspace,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut excluded = HashSet::new();
+    for pattern in workspace.exclude.iter() {
+        for entry in glob::glob(&root.join(pattern).to_string_lossy())? {
+            excluded.insert(entry?);
+        }
+    }
+
+    let mut members = Vec::new();
+    for pattern in workspace.members.iter() {
+        for entry in glob::glob(&root.join(pattern).to_string_lossy())? {
+            let dir = entry?;
+            if !dir.is_dir() || excluded.contains(&dir) {
+                continue;
+            }
+
+            let manifest = dir.join("Cargo.toml");
+            if manifest.exists() {
+                members.push(manifest);
+            }
         }
     }
 
-    Ok(false)
+    Ok(members)
+}
+
+/// The aggregated result of scanning every version of a single crate.
+#[derive(Debug, Serialize)]
+struct CrateReport {
+    name: String,
+    versions: BTreeSet<Version>,
+    bins: Vec<PathBuf>,
+    libs: Vec<PathBuf>,
+    trivial: bool,
+    findings: Vec<Finding>,
+    // The real `main` body line count, independent of the triviality threshold; only used to
+    // answer queries, so it stays out of the serialised record.
+    #[serde(skip)]
+    main_lines: usize,
+}
+
+impl CrateReport {
+    fn scan(
+        name: String,
+        roots: BTreeSet<Root>,
+        config: &config::Config,
+        rules: &rule::Rules,
+    ) -> anyhow::Result<Self> {
+        let mut versions = BTreeSet::new();
+        let mut bins = Vec::new();
+        let mut libs = Vec::new();
+        let mut findings = Vec::new();
+        let mut main_lines = 0;
+
+        for root in roots.iter() {
+            if let Some(package) = &root.manifest.package {
+                versions.insert(package.version.clone());
+            }
+            for bin in root.bins() {
+                if let Some(lines) = main_line_count(&bin)? {
+                    main_lines = main_lines.max(lines);
+                }
+                if !bins.contains(&bin) {
+                    bins.push(bin);
+                }
+            }
+            if let Some(lib) = root.lib() {
+                if !libs.contains(&lib) {
+                    libs.push(lib);
+                }
+            }
+            findings.extend(root.findings(config, rules)?);
+        }
+
+        let trivial = findings.is_empty();
+
+        Ok(Self {
+            name,
+            versions,
+            bins,
+            libs,
+            trivial,
+            findings,
+            main_lines,
+        })
+    }
+
+    /// Derives the flat metadata a [`query::Query`] is evaluated against.
+    fn metadata(&self) -> query::CrateMetadata {
+        let lib_pub_items = self
+            .findings
+            .iter()
+            .filter(|finding| matches!(finding.kind, FindingKind::PubItem { .. }))
+            .count() as i64;
+
+        query::CrateMetadata {
+            name: self.name.clone(),
+            // Queries resolve against the highest version scanned for the crate.
+            version: self
+                .versions
+                .iter()
+                .next_back()
+                .cloned()
+                .unwrap_or_else(|| Version::new(0, 0, 0)),
+            trivial: self.trivial,
+            has_lib: !self.libs.is_empty(),
+            has_bin: !self.bins.is_empty(),
+            bin_count: self.bins.len() as i64,
+            lib_pub_items,
+            main_lines: self.main_lines as i64,
+        }
+    }
+
+    fn emit(&self, opt: &Opt) -> anyhow::Result<()> {
+        match opt.message_format {
+            MessageFormat::Human => {
+                if self.trivial {
+                    println!("{}", self.name);
+                } else if opt.verbose || opt.explain {
+                    // `--explain` forces the header so the indented findings are attributable.
+                    println!("non trivial: {}", self.name);
+                }
+            }
+            MessageFormat::Short => {
+                let label = if self.trivial { "trivial" } else { "non-trivial" };
+                println!("{} {label}", self.name);
+            }
+            MessageFormat::Json => {
+                // Serialising can fail on e.g. non-UTF-8 paths, so propagate the error.
+                println!("{}", serde_json::to_string(self)?);
+                return Ok(());
+            }
+        }
+
+        // `--explain` only makes sense for the textual formats; the JSON record already
+        // carries the findings inline.
+        if opt.explain && !self.trivial {
+            for finding in self.findings.iter() {
+                println!("  {finding}");
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Eq)]
@@ -90,20 +336,20 @@ struct Root {
 }
 
 impl Root {
-    fn has_non_trivial_code(&self) -> anyhow::Result<bool> {
+    fn findings(&self, config: &config::Config, rules: &rule::Rules) -> anyhow::Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+
         for bin in self.bins() {
-            if is_bin_non_trivial(bin)? {
-                return Ok(true);
-            }
+            findings.extend(bin_findings(&bin, &config.bin)?);
+            findings.extend(rule_findings(&bin, rules)?);
         }
 
         if let Some(lib) = self.lib() {
-            if is_lib_non_trivial(lib)? {
-                return Ok(true);
-            }
+            findings.extend(lib_findings(&lib, &config.lib)?);
+            findings.extend(rule_findings(&lib, rules)?);
         }
 
-        Ok(false)
+        Ok(findings)
     }
 
     fn bins(&self) -> impl Iterator<Item = PathBuf> {
@@ -173,13 +419,17 @@ lazy_static! {
     };
 }
 
-fn is_bin_non_trivial(path: impl AsRef<Path>) -> anyhow::Result<bool> {
+fn bin_findings(
+    path: impl AsRef<Path>,
+    config: &config::BinConfig,
+) -> anyhow::Result<Vec<Finding>> {
     // We want:
     //
     // (a) any function other than main, or
-    // (b) a main that has more than one line, or
-    // (c) a main with one line that is not println!
+    // (b) a main whose body exceeds `max_main_lines`, or
+    // (c) a main whose body calls none of the `allowed_trivial_macros`.
 
+    let path = path.as_ref();
     let mut content = Vec::new();
     File::open(path)?.read_to_end(&mut content)?;
 
@@ -187,6 +437,8 @@ fn is_bin_non_trivial(path: impl AsRef<Path>) -> anyhow::Result<bool> {
         .parse(&content, None)
         .ok_or_else(|| anyhow::anyhow!("parsing failed"))?;
     let root = tree.root_node();
+
+    let mut findings = Vec::new();
     for child in root
         .children(&mut root.walk())
         .filter(|node| node.kind() == "function_item")
@@ -196,35 +448,61 @@ fn is_bin_non_trivial(path: impl AsRef<Path>) -> anyhow::Result<bool> {
             .children_by_field_name("name", &mut cursor)
             .next()
             .ok_or_else(|| anyhow::anyhow!("function item does not have a name: {child:?}"))?
-            .utf8_text(&content)?;
+            .utf8_text(&content)?
+            .to_string();
 
         if name != "main" {
-            return Ok(true);
+            findings.push(Finding {
+                file: path.to_path_buf(),
+                kind: FindingKind::ExtraFunction,
+                name: Some(name),
+                line_range: line_range(&child),
+            });
+            continue;
         }
 
         let body = child
             .children_by_field_name("body", &mut cursor)
             .next()
-            .ok_or_else(|| anyhow::anyhow!("function item does not have a body: {child:?}"))?
-            .utf8_text(&content)?;
-
-        if body.chars().filter(|c| *c == '\n').count() > 2 {
-            return Ok(true);
-        }
-
-        if !body.contains("println!") {
-            return Ok(true);
+            .ok_or_else(|| anyhow::anyhow!("function item does not have a body: {child:?}"))?;
+        let body_text = body.utf8_text(&content)?;
+
+        let lines = body_text.chars().filter(|c| *c == '\n').count();
+        let calls_allowed_macro = config
+            .allowed_trivial_macros
+            .iter()
+            .any(|macro_name| body_text.contains(&format!("{macro_name}!")));
+
+        if lines > config.max_main_lines {
+            findings.push(Finding {
+                file: path.to_path_buf(),
+                kind: FindingKind::LargeMain { lines },
+                name: Some(name),
+                line_range: line_range(&body),
+            });
+        } else if !calls_allowed_macro {
+            findings.push(Finding {
+                file: path.to_path_buf(),
+                kind: FindingKind::NonTrivialMain,
+                name: Some(name),
+                line_range: line_range(&body),
+            });
         }
     }
 
-    Ok(false)
+    Ok(findings)
 }
 
-fn is_lib_non_trivial(path: impl AsRef<Path>) -> anyhow::Result<bool> {
+fn lib_findings(
+    path: impl AsRef<Path>,
+    config: &config::LibConfig,
+) -> anyhow::Result<Vec<Finding>> {
     // We want:
     //
-    // (a) literally any pub fn, enum, struct, or type.
+    // (a) literally any pub fn, enum, struct, or type, or
+    // (b) when `trivial_if_no_pub` is disabled, any library at all.
 
+    let path = path.as_ref();
     let mut content = Vec::new();
     File::open(path)?.read_to_end(&mut content)?;
 
@@ -232,6 +510,8 @@ fn is_lib_non_trivial(path: impl AsRef<Path>) -> anyhow::Result<bool> {
         .parse(&content, None)
         .ok_or_else(|| anyhow::anyhow!("parsing failed"))?;
     let root = tree.root_node();
+
+    let mut findings = Vec::new();
     for child in root.children(&mut root.walk()).filter(|node| {
         matches!(
             node.kind(),
@@ -254,12 +534,171 @@ fn is_lib_non_trivial(path: impl AsRef<Path>) -> anyhow::Result<bool> {
             .find(|node| node.kind() == "visibility_modifier")
         {
             if vis.utf8_text(&content)? == "pub" {
-                return Ok(true);
+                let mut name_cursor = child.walk();
+                let name = child
+                    .children_by_field_name("name", &mut name_cursor)
+                    .next()
+                    .and_then(|node| node.utf8_text(&content).ok())
+                    .map(str::to_string);
+
+                findings.push(Finding {
+                    file: path.to_path_buf(),
+                    kind: FindingKind::PubItem {
+                        item: pretty_item_kind(child.kind()).to_string(),
+                    },
+                    name,
+                    line_range: line_range(&child),
+                });
             }
         };
     }
 
-    Ok(false)
+    if findings.is_empty() && !config.trivial_if_no_pub {
+        findings.push(Finding {
+            file: path.to_path_buf(),
+            kind: FindingKind::LibWithoutPub,
+            name: None,
+            line_range: line_range(&root),
+        });
+    }
+
+    Ok(findings)
+}
+
+/// Counts the newlines in a binary's `main` body, if it has one, regardless of whether that
+/// count crosses the triviality threshold.
+fn main_line_count(path: impl AsRef<Path>) -> anyhow::Result<Option<usize>> {
+    let mut content = Vec::new();
+    File::open(path)?.read_to_end(&mut content)?;
+
+    let tree = rust_parser()?
+        .parse(&content, None)
+        .ok_or_else(|| anyhow::anyhow!("parsing failed"))?;
+    let root = tree.root_node();
+
+    for child in root
+        .children(&mut root.walk())
+        .filter(|node| node.kind() == "function_item")
+    {
+        let mut cursor = child.walk();
+        let name = child
+            .children_by_field_name("name", &mut cursor)
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("function item does not have a name: {child:?}"))?
+            .utf8_text(&content)?;
+
+        if name == "main" {
+            let body = child
+                .children_by_field_name("body", &mut cursor)
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("function item does not have a body: {child:?}"))?
+                .utf8_text(&content)?;
+
+            return Ok(Some(body.chars().filter(|c| *c == '\n').count()));
+        }
+    }
+
+    Ok(None)
+}
+
+fn rule_findings(path: &Path, rules: &rule::Rules) -> anyhow::Result<Vec<Finding>> {
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut content = Vec::new();
+    File::open(path)?.read_to_end(&mut content)?;
+
+    let tree = rust_parser()?
+        .parse(&content, None)
+        .ok_or_else(|| anyhow::anyhow!("parsing failed"))?;
+
+    Ok(rules.findings(path, &content, tree.root_node()))
+}
+
+/// A single piece of evidence that a crate is non-trivial, tied back to its source location.
+#[derive(Debug, Serialize)]
+struct Finding {
+    file: PathBuf,
+    kind: FindingKind,
+    name: Option<String>,
+    line_range: Range<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+enum FindingKind {
+    /// A `pub` item exported from a library.
+    PubItem { item: String },
+    /// A function other than `main` in a binary.
+    ExtraFunction,
+    /// A `main` whose body spans more than the permitted number of lines.
+    LargeMain { lines: usize },
+    /// A short `main` whose body calls none of the allowed trivial macros.
+    NonTrivialMain,
+    /// A library carrying no `pub` items, flagged because `trivial_if_no_pub` is disabled.
+    LibWithoutPub,
+    /// A node captured by a user-supplied tree-sitter rule.
+    Rule { rule: String },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let loc = format!(
+            "{}:{}-{}",
+            self.file.display(),
+            self.line_range.start,
+            self.line_range.end
+        );
+
+        match &self.kind {
+            FindingKind::PubItem { item } => match &self.name {
+                Some(name) => write!(f, "pub {item} `{name}` at {loc}"),
+                None => write!(f, "pub {item} at {loc}"),
+            },
+            FindingKind::ExtraFunction => write!(
+                f,
+                "extra function `{}` at {loc}",
+                self.name.as_deref().unwrap_or("?")
+            ),
+            FindingKind::LargeMain { lines } => write!(f, "main body has {lines} lines at {loc}"),
+            FindingKind::NonTrivialMain => {
+                write!(f, "main body calls no allowed trivial macro at {loc}")
+            }
+            FindingKind::LibWithoutPub => write!(f, "library has no pub items at {loc}"),
+            FindingKind::Rule { rule } => match &self.name {
+                Some(kind) => write!(f, "matched rule `{rule}` ({kind}) at {loc}"),
+                None => write!(f, "matched rule `{rule}` at {loc}"),
+            },
+        }
+    }
+}
+
+/// The one-based line range covered by a tree-sitter node; both `start` and `end` are inclusive,
+/// so a node on a single line `n` yields `n..n`.
+fn line_range(node: &tree_sitter::Node) -> Range<usize> {
+    (node.start_position().row + 1)..(node.end_position().row + 1)
+}
+
+/// Maps a tree-sitter item kind onto the keyword a reader would recognise.
+fn pretty_item_kind(kind: &str) -> &'static str {
+    match kind {
+        "function_item" => "fn",
+        "const_item" => "const",
+        "enum_item" => "enum",
+        "foreign_mod_item" => "extern block",
+        "mod_item" => "mod",
+        "struct_item" => "struct",
+        "static_item" => "static",
+        "trait_item" => "trait",
+        "type_item" => "type",
+        "use_declaration" => "use",
+        other => {
+            // Should be unreachable given the filter above, but degrade gracefully.
+            debug_assert!(false, "unexpected item kind: {other}");
+            "item"
+        }
+    }
 }
 
 fn is_manifest(path: &OsStr) -> bool {
@@ -274,9 +713,10 @@ fn rust_parser() -> anyhow::Result<tree_sitter::Parser> {
 
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 struct Manifest {
-    package: Package,
+    package: Option<Package>,
     lib: Option<Lib>,
     bins: Option<Vec<Bin>>,
+    workspace: Option<Workspace>,
 }
 
 impl Ord for Manifest {
@@ -297,6 +737,14 @@ struct Package {
     version: Version,
 }
 
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 struct Lib {
     path: Option<PathBuf>,
@@ -306,3 +754,48 @@ struct Lib {
 struct Bin {
     path: Option<PathBuf>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn temp_dir() -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("triviality-scan-{}-{id}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn workspace_members_are_resolved_and_vendored_manifests_pruned() {
+        let dir = temp_dir();
+
+        // A virtual workspace root (no `[package]`) listing a single member.
+        write(&dir.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(
+            &dir.join("crates/a/Cargo.toml"),
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\n",
+        );
+        // A vendored manifest nested inside the member; it must be pruned.
+        write(
+            &dir.join("crates/a/vendor/dep/Cargo.toml"),
+            "[package]\nname = \"dep\"\nversion = \"2.0.0\"\n",
+        );
+
+        let mut crate_roots = HashMap::new();
+        collect_from_paths(&[dir], &mut crate_roots).unwrap();
+
+        assert!(crate_roots.contains_key("a"));
+        assert!(!crate_roots.contains_key("dep"));
+        // The virtual root contributes no package of its own.
+        assert_eq!(crate_roots.len(), 1);
+    }
+}
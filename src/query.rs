@@ -0,0 +1,466 @@
+//! A tiny boolean query language for selecting crates by their computed properties.
+//!
+//! Queries are written as infix boolean expressions over a fixed set of per-crate fields, for
+//! example:
+//!
+//! ```text
+//! trivial == false and version >= "1.0.0" and name =~ "^serde"
+//! ```
+//!
+//! The grammar is a conventional precedence-climbing recursive descent:
+//!
+//! ```text
+//! expr    := or
+//! or      := and ( "or" and )*
+//! and     := not ( "and" not )*
+//! not     := "not" not | primary
+//! primary := "(" expr ")" | cmp
+//! cmp     := field op value
+//! ```
+
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+use regex::Regex;
+use semver::Version;
+
+/// The metadata a query is evaluated against, one value set per scanned crate.
+#[derive(Debug)]
+pub struct CrateMetadata {
+    pub name: String,
+    pub version: Version,
+    pub trivial: bool,
+    pub has_lib: bool,
+    pub has_bin: bool,
+    pub bin_count: i64,
+    pub lib_pub_items: i64,
+    pub main_lines: i64,
+}
+
+/// A compiled query, ready to be evaluated against any number of crates.
+#[derive(Debug)]
+pub struct Query(Expr);
+
+impl Query {
+    /// Parses and type-checks a query expression, compiling any embedded regexes.
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = lex(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_expr()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected trailing input in query: {token:?}");
+        }
+        Ok(Self(expr))
+    }
+
+    /// Returns whether a crate's metadata satisfies the query.
+    pub fn matches(&self, meta: &CrateMetadata) -> bool {
+        self.0.eval(meta)
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Cmp(Cmp),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Group(Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, meta: &CrateMetadata) -> bool {
+        match self {
+            Expr::Cmp(cmp) => cmp.eval(meta),
+            Expr::And(lhs, rhs) => lhs.eval(meta) && rhs.eval(meta),
+            Expr::Or(lhs, rhs) => lhs.eval(meta) || rhs.eval(meta),
+            Expr::Not(inner) => !inner.eval(meta),
+            Expr::Group(inner) => inner.eval(meta),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Cmp {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+impl Cmp {
+    fn eval(&self, meta: &CrateMetadata) -> bool {
+        match (self.field, &self.value) {
+            (Field::Name, Value::Regex(re)) => re.is_match(&meta.name),
+            (Field::Name, Value::Str(s)) => ord_matches(meta.name.as_str().cmp(s.as_str()), self.op),
+            (Field::Version, Value::Version(v)) => ord_matches(meta.version.cmp(v), self.op),
+            (Field::Trivial, Value::Bool(b)) => bool_matches(meta.trivial, *b, self.op),
+            (Field::HasLib, Value::Bool(b)) => bool_matches(meta.has_lib, *b, self.op),
+            (Field::HasBin, Value::Bool(b)) => bool_matches(meta.has_bin, *b, self.op),
+            (Field::BinCount, Value::Num(n)) => ord_matches(meta.bin_count.cmp(n), self.op),
+            (Field::LibPubItems, Value::Num(n)) => ord_matches(meta.lib_pub_items.cmp(n), self.op),
+            (Field::MainLines, Value::Num(n)) => ord_matches(meta.main_lines.cmp(n), self.op),
+            // Parsing guarantees the field/value pairing, so this is unreachable.
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Name,
+    Version,
+    Trivial,
+    HasLib,
+    HasBin,
+    BinCount,
+    LibPubItems,
+    MainLines,
+}
+
+impl Field {
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "name" => Field::Name,
+            "version" => Field::Version,
+            "trivial" => Field::Trivial,
+            "has_lib" => Field::HasLib,
+            "has_bin" => Field::HasBin,
+            "bin_count" => Field::BinCount,
+            "lib_pub_items" => Field::LibPubItems,
+            "main_lines" => Field::MainLines,
+            other => bail!("unknown field `{other}` in query"),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Match,
+}
+
+#[derive(Debug)]
+enum Value {
+    Str(String),
+    Num(i64),
+    Bool(bool),
+    Version(Version),
+    Regex(Regex),
+}
+
+fn ord_matches(ordering: Ordering, op: Op) -> bool {
+    match op {
+        Op::Eq => ordering == Ordering::Equal,
+        Op::Ne => ordering != Ordering::Equal,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Le => ordering != Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+        // Regex matching never reaches the ordering comparison.
+        Op::Match => false,
+    }
+}
+
+fn bool_matches(lhs: bool, rhs: bool, op: Op) -> bool {
+    match op {
+        Op::Eq => lhs == rhs,
+        Op::Ne => lhs != rhs,
+        // Ordering a boolean is rejected during parsing.
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Op(Op),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+}
+
+fn lex(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(ch) => value.push(ch),
+                        None => bail!("unterminated string literal in query"),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            '=' => {
+                chars.next();
+                match chars.next() {
+                    Some('=') => tokens.push(Token::Op(Op::Eq)),
+                    Some('~') => tokens.push(Token::Op(Op::Match)),
+                    other => bail!("expected `==` or `=~`, found `={}`", fmt_opt(other)),
+                }
+            }
+            '!' => {
+                chars.next();
+                match chars.next() {
+                    Some('=') => tokens.push(Token::Op(Op::Ne)),
+                    other => bail!("expected `!=`, found `!{}`", fmt_opt(other)),
+                }
+            }
+            '<' => {
+                chars.next();
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    tokens.push(Token::Op(Op::Le));
+                } else {
+                    tokens.push(Token::Op(Op::Lt));
+                }
+            }
+            '>' => {
+                chars.next();
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    tokens.push(Token::Op(Op::Ge));
+                } else {
+                    tokens.push(Token::Op(Op::Gt));
+                }
+            }
+            _ if is_ident_char(c) => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if is_ident_char(ch) {
+                        word.push(ch);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(word),
+                });
+            }
+            other => bail!("unexpected character `{other}` in query"),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn fmt_opt(c: Option<char>) -> String {
+    c.map(String::from).unwrap_or_else(|| "<eof>".to_string())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_or()
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_and()?;
+        while matches!(self.peek(), Some(Token::Or)) {
+            self.next();
+            let rhs = self.parse_and()?;
+            expr = Expr::Or(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_not()?;
+        while matches!(self.peek(), Some(Token::And)) {
+            self.next();
+            let rhs = self.parse_not()?;
+            expr = Expr::And(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if matches!(self.peek(), Some(Token::Not)) {
+            self.next();
+            let inner = self.parse_not()?;
+            Ok(Expr::Not(Box::new(inner)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        if matches!(self.peek(), Some(Token::LParen)) {
+            self.next();
+            let inner = self.parse_expr()?;
+            match self.next() {
+                Some(Token::RParen) => Ok(Expr::Group(Box::new(inner))),
+                _ => bail!("expected `)` to close group in query"),
+            }
+        } else {
+            self.parse_cmp().map(Expr::Cmp)
+        }
+    }
+
+    fn parse_cmp(&mut self) -> Result<Cmp> {
+        let field = match self.next() {
+            Some(Token::Ident(name)) => Field::parse(&name)?,
+            other => bail!("expected a field name in query, found {other:?}"),
+        };
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            other => bail!("expected a comparison operator in query, found {other:?}"),
+        };
+
+        let raw = match self.next() {
+            Some(Token::Ident(value) | Token::Str(value)) => value,
+            other => bail!("expected a value in query, found {other:?}"),
+        };
+
+        let value = build_value(field, op, raw)?;
+        Ok(Cmp { field, op, value })
+    }
+}
+
+/// Type-checks the `field op value` triple and converts the literal into a typed [`Value`].
+fn build_value(field: Field, op: Op, raw: String) -> Result<Value> {
+    if op == Op::Match {
+        return match field {
+            Field::Name => Ok(Value::Regex(Regex::new(&raw)?)),
+            _ => bail!("`=~` is only supported on the `name` field"),
+        };
+    }
+
+    Ok(match field {
+        Field::Name => Value::Str(raw),
+        Field::Version => Value::Version(Version::parse(&raw)?),
+        Field::Trivial | Field::HasLib | Field::HasBin => {
+            if !matches!(op, Op::Eq | Op::Ne) {
+                bail!("boolean fields only support `==` and `!=`");
+            }
+            Value::Bool(parse_bool(&raw)?)
+        }
+        Field::BinCount | Field::LibPubItems | Field::MainLines => Value::Num(raw.parse()?),
+    })
+}
+
+fn parse_bool(raw: &str) -> Result<bool> {
+    match raw {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => bail!("expected `true` or `false`, found `{other}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> CrateMetadata {
+        CrateMetadata {
+            name: "serde_json".to_string(),
+            version: Version::new(1, 2, 0),
+            trivial: false,
+            has_lib: true,
+            has_bin: true,
+            bin_count: 1,
+            lib_pub_items: 3,
+            main_lines: 0,
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let meta = metadata();
+        // Parsed as `has_bin == true or (has_lib == true and trivial == true)`, which is true;
+        // the wrong `(... or ...) and ...` grouping would be false.
+        let query = Query::parse("has_bin == true or has_lib == true and trivial == true").unwrap();
+        assert!(query.matches(&meta));
+    }
+
+    #[test]
+    fn not_and_grouping() {
+        let meta = metadata();
+        assert!(Query::parse("not (trivial == true)").unwrap().matches(&meta));
+        assert!(!Query::parse("not (trivial == false)").unwrap().matches(&meta));
+    }
+
+    #[test]
+    fn version_uses_semver_ordering() {
+        let meta = metadata();
+        assert!(Query::parse("version >= \"1.0.0\"").unwrap().matches(&meta));
+        assert!(!Query::parse("version >= \"2.0.0\"").unwrap().matches(&meta));
+    }
+
+    #[test]
+    fn regex_matches_name() {
+        let meta = metadata();
+        assert!(Query::parse("name =~ \"^serde\"").unwrap().matches(&meta));
+        assert!(!Query::parse("name =~ \"^tokio\"").unwrap().matches(&meta));
+    }
+
+    #[test]
+    fn unknown_field_is_an_error() {
+        assert!(Query::parse("bogus == 1").is_err());
+    }
+
+    #[test]
+    fn unterminated_string_is_an_error() {
+        assert!(Query::parse("name =~ \"serde").is_err());
+    }
+
+    #[test]
+    fn regex_only_allowed_on_name() {
+        assert!(Query::parse("version =~ \"1\"").is_err());
+    }
+
+    #[test]
+    fn ordering_a_boolean_is_an_error() {
+        assert!(Query::parse("trivial < true").is_err());
+    }
+}
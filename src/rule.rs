@@ -0,0 +1,137 @@
+//! User-supplied tree-sitter queries used as pluggable non-triviality detection rules.
+//!
+//! Each rule file is an S-expression [`tree_sitter::Query`] with a capture named `@nontrivial`,
+//! for example:
+//!
+//! ```scheme
+//! (function_item name: (identifier) @nontrivial)
+//! ```
+//!
+//! Any capture produced when running the query over a crate's parse tree marks that crate
+//! non-trivial, and the captured node supplies the span reported by `--explain`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use tree_sitter::{Query, QueryCursor};
+
+use crate::{line_range, Finding, FindingKind};
+
+/// A set of compiled detection rules, compiled once and reused across every scanned file.
+pub struct Rules {
+    rules: Vec<CompiledRule>,
+}
+
+struct CompiledRule {
+    name: String,
+    query: Query,
+    capture_index: u32,
+}
+
+impl Rules {
+    /// Compiles each rule file, requiring every query to declare a `@nontrivial` capture.
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let language = tree_sitter_rust::language();
+
+        let mut rules = Vec::new();
+        for path in paths.iter() {
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("reading rule file {path:?}"))?;
+            let query = Query::new(language, &source)
+                .with_context(|| format!("compiling rule file {path:?}"))?;
+            let capture_index = query
+                .capture_index_for_name("nontrivial")
+                .ok_or_else(|| anyhow::anyhow!("rule file {path:?} has no @nontrivial capture"))?;
+
+            rules.push(CompiledRule {
+                name: path.display().to_string(),
+                query,
+                capture_index,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Runs every rule over a parsed file, yielding a [`Finding`] per `@nontrivial` capture.
+    pub fn findings(&self, file: &Path, content: &[u8], root: tree_sitter::Node) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut cursor = QueryCursor::new();
+
+        for rule in self.rules.iter() {
+            for m in cursor.matches(&rule.query, root, content) {
+                for capture in m.captures {
+                    if capture.index == rule.capture_index {
+                        findings.push(Finding {
+                            file: file.to_path_buf(),
+                            kind: FindingKind::Rule {
+                                rule: rule.name.clone(),
+                            },
+                            name: Some(capture.node.kind().to_string()),
+                            line_range: line_range(&capture.node),
+                        });
+                    }
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn rule_file(contents: &str) -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("triviality-rule-{}-{id}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rule.scm");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn parse(source: &[u8]) -> tree_sitter::Tree {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(tree_sitter_rust::language()).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn capture_produces_rule_finding_with_span() {
+        let path = rule_file("(function_item) @nontrivial");
+        let rules = Rules::load(&[path.clone()]).unwrap();
+
+        let source = b"fn foo() {}\n";
+        let tree = parse(source);
+        let findings = rules.findings(Path::new("lib.rs"), source, tree.root_node());
+
+        assert_eq!(findings.len(), 1);
+        assert!(matches!(findings[0].kind, FindingKind::Rule { .. }));
+        // The whole `fn foo() {}` lives on the first line.
+        assert_eq!(findings[0].line_range, 1..1);
+    }
+
+    #[test]
+    fn missing_nontrivial_capture_is_an_error() {
+        let path = rule_file("(function_item) @other");
+        assert!(Rules::load(&[path]).is_err());
+    }
+
+    #[test]
+    fn invalid_query_is_an_error() {
+        let path = rule_file("(this_is_not_a_node) @nontrivial");
+        assert!(Rules::load(&[path]).is_err());
+    }
+}